@@ -8,7 +8,10 @@ use syn::parse::discouraged::Speculative;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::token::{self, Colon, Comma};
-use syn::{bracketed, Attribute, Generics, Ident, LitStr, Result, Type, Visibility};
+use syn::{
+    bracketed, parenthesized, parse_quote, Attribute, GenericParam, Generics, Ident, LitStr,
+    Result, Type, Visibility,
+};
 
 /// This macro allows quickly defining errors in the format that this crate produces.
 ///
@@ -49,6 +52,35 @@ use syn::{bracketed, Attribute, Generics, Ident, LitStr, Result, Type, Visibilit
 ///     MyStructError,
 /// }
 /// ```
+/// Individual variants can carry their own message instead of falling back to the enum's shared
+/// one, with `{0}` interpolating the wrapped source error. A variant's message doesn't have to
+/// interpolate the source at all:
+/// ```
+/// # use quickerr::error;
+/// # error! { SourceError1 "" }
+/// # error! { SourceError2 "" }
+/// # error! { MyUnitError "" }
+/// error! {
+///     MyMixedEnumError
+///     "it's a whole enum"
+///     SourceError1: "failed with a source error: {0}",
+///     SourceError2: "failed with no further detail",
+///     MyUnitError,
+/// }
+/// ```
+/// A variant's name doesn't have to match the type it wraps: write `Name(Type)` instead of the
+/// bare `Type` shorthand to give it a different name, which also lets two variants wrap the same
+/// type. The `From` impl for a wrapped type is skipped when more than one variant wraps it, since
+/// the conversion would be ambiguous; construct those variants explicitly instead:
+/// ```
+/// # use quickerr::error;
+/// error! {
+///     MultiIoError
+///     "multiple things can go wrong with io"
+///     ReadFailed(std::io::Error): "failed to read: {0}",
+///     WriteFailed(std::io::Error): "failed to write: {0}",
+/// }
+/// ```
 /// - Transparent enum:
 /// ```
 /// # use quickerr::error;
@@ -72,12 +104,30 @@ use syn::{bracketed, Attribute, Generics, Ident, LitStr, Result, Type, Visibilit
 /// ```
 ///
 /// Each form implements `Debug`, `Error`, and `From` as appropriate. The enum forms implement
-/// [`std::error::Error::source()`] for each of their variants, and each variant must be the name
-/// of an existing error type. The struct form exposes the fields for use in the error message.
-/// The transparent enum form does not append a message, and simply passes the source along
+/// [`std::error::Error::source()`] for each of their variants, and each variant wraps an existing
+/// error type, named after that type unless given an explicit `Name(Type)`. The struct form
+/// exposes the fields for use in the error message. The transparent enum form does not append a
+/// message, and simply passes the source along
 /// directly. All forms are `#[non_exhaustive]` and all fields are public. They can be made public
 /// by adding `pub` to the name like `pub MyError`.
 ///
+/// The struct form can mark exactly one field `#[source]` to have it returned from
+/// [`std::error::Error::source()`], wiring the struct into the standard error chain:
+/// ```
+/// # use quickerr::error;
+/// # error! { Inner "inner" }
+/// error! {
+///     Outer
+///     "something went wrong: {detail}"
+///     #[source]
+///     cause: Inner,
+///     detail: String,
+/// }
+/// ```
+/// Adding `#[from]` alongside (or instead of) `#[source]` additionally generates a `From` impl for
+/// the field's type; this only works if the `#[source]`/`#[from]` field is the struct's only
+/// field, since there's no way to fill in the others.
+///
 /// Additional attributes can be added before the name to add them to the error type,
 /// like so:
 /// ```
@@ -102,6 +152,19 @@ use syn::{bracketed, Attribute, Generics, Ident, LitStr, Result, Type, Visibilit
 ///     like_this_one: BreakingTool,
 /// }
 /// ```
+/// Bounds needed only for interpolating a field into the message don't need to be written out:
+/// for Struct and Enum errors, `error!` inspects the message string and infers `Display`/`Debug`
+/// bounds for any generic type parameter interpolated through a field (`{field}` needs `Display`,
+/// `{field:?}` needs `Debug`). A field type still needs `Debug` for `#[derive(Debug)]` to apply,
+/// but no bound needs to be written by hand just to print the field:
+/// ```
+/// # use quickerr::error;
+/// error! {
+///     BreakGlass2<BreakingTool: std::fmt::Debug>
+///     "preferably with a blunt object: {like_this_one}"
+///     like_this_one: BreakingTool,
+/// }
+/// ```
 ///
 /// If cfg attributes are used, they're copied to relevant places to ensure it compiles properly:
 /// ```
@@ -130,6 +193,11 @@ use syn::{bracketed, Attribute, Generics, Ident, LitStr, Result, Type, Visibilit
 /// ```
 /// Make sure not to use cfg'd fields in the error message string if those fields can ever be not
 /// present.
+///
+/// Enabling the `no_std` feature on this crate makes `error!` emit `core`/`alloc` paths
+/// (`::core::fmt`, `::core::error::Error`, `::alloc::vec::Vec`, ...) instead of `std` ones, so the
+/// generated types work in `#![no_std]` crates. Callers still need `extern crate alloc;` in scope
+/// for the array form.
 #[proc_macro]
 pub fn error(tokens: TokenStream) -> TokenStream {
     match error_impl(tokens.into()) {
@@ -150,20 +218,26 @@ fn error_impl(tokens: TokenStream2) -> Result<TokenStream2> {
 
     let (impl_gen, ty_gen, where_gen) = generics.split_for_impl();
 
-    Ok(match contents {
+    let fmt_path = core_path(quote!(fmt));
+    let error_path = core_path(quote!(error::Error));
+    let convert_path = core_path(quote!(convert));
+    let option_path = core_path(quote!(option::Option));
+    let vec_path = alloc_path(quote!(vec::Vec));
+
+    let tokens = match contents {
         ErrorContents::Unit => quote! {
             #(#attrs)*
             #[derive(Debug)]
             #[non_exhaustive]
             #vis struct #name #generics;
 
-            impl #impl_gen ::std::fmt::Display for #name #ty_gen #where_gen {
-                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+            impl #impl_gen #fmt_path::Display for #name #ty_gen #where_gen {
+                fn fmt(&self, f: &mut #fmt_path::Formatter<'_>) -> #fmt_path::Result {
                     f.write_str(#msg)
                 }
             }
 
-            impl #impl_gen ::std::error::Error for #name #ty_gen #where_gen {}
+            impl #impl_gen #error_path for #name #ty_gen #where_gen {}
         },
         ErrorContents::Struct { fields } => {
             let cfgs: Vec<Vec<&Attribute>> = fields
@@ -177,17 +251,75 @@ fn error_impl(tokens: TokenStream2) -> Result<TokenStream2> {
                 })
                 .collect();
             let field_names: Vec<&Ident> = fields.iter().map(|field| &field.name).collect();
+            let display_generics = with_display_bounds(
+                &generics,
+                msg.as_ref(),
+                fields.iter().map(|field| (&field.name, &field.ty)),
+            );
+            let (_, _, display_where_gen) = display_generics.split_for_impl();
+
+            let mut source_field = None;
+            for field in fields.iter() {
+                let has_source = field.attrs.iter().any(|a| a.meta.path().is_ident("source"));
+                let has_from = field.attrs.iter().any(|a| a.meta.path().is_ident("from"));
+                if has_source || has_from {
+                    if source_field.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            &field.name,
+                            "at most one field may be marked #[source] or #[from]",
+                        ));
+                    }
+                    source_field = Some((field, has_from));
+                }
+            }
+
+            let source_impl = source_field.map(|(field, _)| &field.name).map(|field_name| {
+                quote! {
+                    fn source(&self) -> #option_path<&(dyn #error_path + 'static)> {
+                        Some(&self.#field_name)
+                    }
+                }
+            });
+
+            let from_impl = match source_field {
+                Some((field, true)) => {
+                    if fields.len() > 1 {
+                        return Err(syn::Error::new_spanned(
+                            &field.name,
+                            "#[from] requires the #[source] field to be the struct's only field",
+                        ));
+                    }
+                    let field_name = &field.name;
+                    let field_ty = &field.ty;
+                    Some(quote! {
+                        impl #impl_gen #convert_path::From<#field_ty> for #name #ty_gen #where_gen {
+                            fn from(#field_name: #field_ty) -> Self {
+                                Self { #field_name }
+                            }
+                        }
+                    })
+                }
+                _ => None,
+            };
+
+            let mut emit_fields = fields.clone();
+            for field in emit_fields.iter_mut() {
+                field
+                    .attrs
+                    .retain(|attr| !attr.meta.path().is_ident("source") && !attr.meta.path().is_ident("from"));
+            }
+
             quote! {
                 #(#attrs)*
                 #[derive(Debug)]
                 #[non_exhaustive]
                 #vis struct #name #generics {
-                    #fields
+                    #emit_fields
                 }
 
-                impl #impl_gen ::std::fmt::Display for #name #ty_gen #where_gen {
+                impl #impl_gen #fmt_path::Display for #name #ty_gen #display_where_gen {
                     #[allow(unused_variables)]
-                    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    fn fmt(&self, f: &mut #fmt_path::Formatter<'_>) -> #fmt_path::Result {
                         let Self {
                             #(
                                 #(#cfgs)*
@@ -198,7 +330,11 @@ fn error_impl(tokens: TokenStream2) -> Result<TokenStream2> {
                     }
                 }
 
-                impl #impl_gen ::std::error::Error for #name #ty_gen #where_gen {}
+                impl #impl_gen #error_path for #name #ty_gen #display_where_gen {
+                    #source_impl
+                }
+
+                #from_impl
             }
         }
         ErrorContents::Enum { sources } => {
@@ -213,22 +349,63 @@ fn error_impl(tokens: TokenStream2) -> Result<TokenStream2> {
                 })
                 .collect();
             let source_idents: Vec<&Ident> = sources.iter().map(|source| &source.ident).collect();
-            let write_msg = match &msg {
-                Some(msg) => quote! {
-                    f.write_str(#msg)
-                },
-                None => {
-                    quote! {
-                        match self {
-                            #(
-                                #(#cfgs)*
-                                Self::#source_idents(err) => ::std::fmt::Display::fmt(err, f),
-                            )*
-                            _ => unreachable!(),
-                        }
+            let source_tys: Vec<&Type> = sources.iter().map(|source| &source.ty).collect();
+            let mut ty_counts: std::collections::HashMap<String, usize> = Default::default();
+            for ty in &source_tys {
+                *ty_counts.entry(quote!(#ty).to_string()).or_insert(0) += 1;
+            }
+            let from_impls: Vec<Option<TokenStream2>> = sources
+                .iter()
+                .zip(&cfgs)
+                .map(|(source, cfg)| {
+                    let ident = &source.ident;
+                    let ty = &source.ty;
+                    if ty_counts[&quote!(#ty).to_string()] > 1 {
+                        return None;
                     }
+                    Some(quote! {
+                        #(#cfg)*
+                        impl #impl_gen #convert_path::From<#ty> for #name #ty_gen #where_gen {
+                            fn from(source: #ty) -> Self {
+                                Self::#ident(source)
+                            }
+                        }
+                    })
+                })
+                .collect();
+            let variant_arms: Vec<TokenStream2> = sources
+                .iter()
+                .map(|source| match &source.msg {
+                    Some(variant_msg) if format_references_positional_arg(variant_msg) => quote! {
+                        f.write_fmt(format_args!(#variant_msg, err))
+                    },
+                    Some(variant_msg) => quote! {
+                        {
+                            let _ = err;
+                            f.write_fmt(format_args!(#variant_msg))
+                        }
+                    },
+                    None => match &msg {
+                        Some(msg) => quote! {
+                            f.write_str(#msg)
+                        },
+                        None => quote! {
+                            #fmt_path::Display::fmt(err, f)
+                        },
+                    },
+                })
+                .collect();
+            let write_msg = quote! {
+                match self {
+                    #(
+                        #(#cfgs)*
+                        Self::#source_idents(err) => #variant_arms,
+                    )*
+                    _ => unreachable!(),
                 }
             };
+            let display_generics = with_display_bounds(&generics, msg.as_ref(), std::iter::empty());
+            let (_, _, display_where_gen) = display_generics.split_for_impl();
             quote! {
                 #(#attrs)*
                 #[derive(Debug)]
@@ -236,18 +413,18 @@ fn error_impl(tokens: TokenStream2) -> Result<TokenStream2> {
                 #vis enum #name #generics {
                     #(
                         #(#source_attrs)*
-                        #source_idents(#source_idents),
+                        #source_idents(#source_tys),
                     )*
                 }
 
-                impl #impl_gen ::std::fmt::Display for #name #ty_gen #where_gen {
-                    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                impl #impl_gen #fmt_path::Display for #name #ty_gen #display_where_gen {
+                    fn fmt(&self, f: &mut #fmt_path::Formatter<'_>) -> #fmt_path::Result {
                         #write_msg
                     }
                 }
 
-                impl #impl_gen ::std::error::Error for #name #ty_gen #where_gen {
-                    fn source(&self) -> ::std::option::Option<&(dyn ::std::error::Error + 'static)> {
+                impl #impl_gen #error_path for #name #ty_gen #display_where_gen {
+                    fn source(&self) -> #option_path<&(dyn #error_path + 'static)> {
                         Some(match self {
                             #(
                                 #(#cfgs)*
@@ -258,14 +435,7 @@ fn error_impl(tokens: TokenStream2) -> Result<TokenStream2> {
                     }
                 }
 
-                #(
-                    #(#cfgs)*
-                    impl #impl_gen ::std::convert::From<#source_idents> for #name #ty_gen #where_gen {
-                        fn from(source: #source_idents) -> Self {
-                            Self::#source_idents(source)
-                        }
-                    }
-                )*
+                #(#from_impls)*
             }
         }
         ErrorContents::Array {
@@ -274,10 +444,10 @@ fn error_impl(tokens: TokenStream2) -> Result<TokenStream2> {
             #(#attrs)*
             #[derive(Debug)]
             #[non_exhaustive]
-            #vis struct #name #generics (#(#inner_attrs)* pub Vec<#inner>);
+            #vis struct #name #generics (#(#inner_attrs)* pub #vec_path<#inner>);
 
-            impl #impl_gen ::std::fmt::Display for #name #ty_gen #where_gen {
-                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+            impl #impl_gen #fmt_path::Display for #name #ty_gen #where_gen {
+                fn fmt(&self, f: &mut #fmt_path::Formatter<'_>) -> #fmt_path::Result {
                     f.write_str(#msg)?;
                     f.write_str(":")?;
                     for err in &self.0 {
@@ -288,11 +458,142 @@ fn error_impl(tokens: TokenStream2) -> Result<TokenStream2> {
                 }
             }
 
-            impl #impl_gen ::std::error::Error for #name #ty_gen #where_gen {}
+            impl #impl_gen #error_path for #name #ty_gen #where_gen {}
         },
-    })
+    };
+    Ok(tokens)
+}
+
+/// Returns the `core`- or `std`-rooted path for `suffix`, depending on whether the `no_std`
+/// feature is enabled.
+fn core_path(suffix: TokenStream2) -> TokenStream2 {
+    if cfg!(feature = "no_std") {
+        quote!(::core::#suffix)
+    } else {
+        quote!(::std::#suffix)
+    }
+}
+
+/// Returns the `alloc`- or `std`-rooted path for `suffix`, depending on whether the `no_std`
+/// feature is enabled.
+fn alloc_path(suffix: TokenStream2) -> TokenStream2 {
+    if cfg!(feature = "no_std") {
+        quote!(::alloc::#suffix)
+    } else {
+        quote!(::std::#suffix)
+    }
+}
+
+/// Scans a format string for `{name}`/`{name:spec}` runs (honoring `{{`/`}}` escapes) and returns
+/// each referenced argument name (empty for `{}`) along with its raw format spec (empty if none).
+fn format_arg_raw_specs(msg: &LitStr) -> Vec<(String, String)> {
+    let value = msg.value();
+    let chars: Vec<char> = value.chars().collect();
+    let mut specs = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '{' if chars.get(i + 1) == Some(&'{') => i += 2,
+            '}' if chars.get(i + 1) == Some(&'}') => i += 2,
+            '{' => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end] != '}' {
+                    end += 1;
+                }
+                let spec: String = chars[start..end].iter().collect();
+                let (name, fmt_spec) = spec.split_once(':').unwrap_or((spec.as_str(), ""));
+                specs.push((name.to_string(), fmt_spec.to_string()));
+                i = end + 1;
+            }
+            _ => i += 1,
+        }
+    }
+    specs
 }
 
+fn format_arg_specs(msg: &LitStr) -> Vec<(String, bool)> {
+    format_arg_raw_specs(msg)
+        .into_iter()
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, fmt_spec)| (name, fmt_spec.contains('?')))
+        .collect()
+}
+
+/// Whether `msg` interpolates its single positional argument, via `{}` or the explicit `{0}`.
+fn format_references_positional_arg(msg: &LitStr) -> bool {
+    format_arg_raw_specs(msg)
+        .iter()
+        .any(|(name, _)| name.is_empty() || name == "0")
+}
+
+/// Returns `ty`'s identifier if it's a single bare path segment with no generic arguments of its
+/// own, e.g. `T` but not `T<U>` or `std::T`.
+fn bare_ident(ty: &Type) -> Option<&Ident> {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() && type_path.path.segments.len() == 1 => {
+            let segment = &type_path.path.segments[0];
+            segment.arguments.is_empty().then_some(&segment.ident)
+        }
+        _ => None,
+    }
+}
+
+/// Clones `generics`, adding a `Display`/`Debug` bound for each generic type parameter that `msg`
+/// interpolates through one of `fields`, and returns the result for use in the `where` clause of
+/// the `Display` impl and of the `Error` impl, which needs `Display` as a supertrait. The plain
+/// `generics` passed in are left untouched for the `From` impls, which don't need these bounds.
+fn with_display_bounds<'a>(
+    generics: &Generics,
+    msg: Option<&LitStr>,
+    fields: impl Iterator<Item = (&'a Ident, &'a Type)>,
+) -> Generics {
+    let mut display_generics = generics.clone();
+    let Some(msg) = msg else {
+        return display_generics;
+    };
+
+    let params: Vec<&Ident> = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(type_param) => Some(&type_param.ident),
+            _ => None,
+        })
+        .collect();
+    let fields: Vec<(&Ident, &Type)> = fields.collect();
+
+    let mut seen = std::collections::HashSet::new();
+    for (arg_name, is_debug) in format_arg_specs(msg) {
+        let Some(&(_, ty)) = fields.iter().find(|(name, _)| **name == arg_name) else {
+            continue;
+        };
+        let Some(ty_ident) = bare_ident(ty) else {
+            continue;
+        };
+        if !params.iter().any(|param| **param == *ty_ident) {
+            continue;
+        }
+
+        if !seen.insert((ty_ident.clone(), is_debug)) {
+            continue;
+        }
+        if is_debug {
+            display_generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote!(#ty_ident: ::core::fmt::Debug));
+        } else {
+            display_generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote!(#ty_ident: ::core::fmt::Display));
+        }
+    }
+    display_generics
+}
+
+#[derive(Clone)]
 struct Field {
     attrs: Vec<Attribute>,
     vis: Visibility,
@@ -328,13 +629,35 @@ impl ToTokens for Field {
 struct ErrorVariant {
     attrs: Vec<Attribute>,
     ident: Ident,
+    ty: Type,
+    msg: Option<LitStr>,
 }
 
 impl Parse for ErrorVariant {
     fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let ident: Ident = input.parse()?;
+        let ty = if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            content.parse()?
+        } else {
+            Type::Path(syn::TypePath {
+                qself: None,
+                path: ident.clone().into(),
+            })
+        };
+        let msg = if input.peek(Colon) {
+            let _: Colon = input.parse()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
         Ok(Self {
-            attrs: input.call(Attribute::parse_outer)?,
-            ident: input.parse()?,
+            attrs,
+            ident,
+            ty,
+            msg,
         })
     }
 }